@@ -0,0 +1,98 @@
+//! Reusable `deserialize_with` helpers for the HLAPI's loose JSON.
+//!
+//! Numeric fields sometimes arrive as a string instead of a number, and descriptor `type`/kind
+//! tags don't always match the Rust variant names' casing. Apply these as
+//! `#[serde(deserialize_with = "de::...")]` on the field itself, e.g.:
+//!
+//! ```ignore
+//! #[derive(Deserialize)]
+//! struct DeviceDescriptor {
+//!     #[serde(deserialize_with = "de::string_or_number")]
+//!     slot: u32,
+//!     #[serde(deserialize_with = "de::case_insensitive_enum")]
+//!     kind: DeviceKind,
+//!     // ...
+//! }
+//! ```
+//!
+//! TODO: neither helper is actually wired onto `DeviceDescriptor` or `MethodDescriptor` yet --
+//! those types aren't in this checkout to annotate. Needs a human to apply the attributes once
+//! they're available (and to confirm which fields actually need the leniency).
+
+use std::{fmt, str::FromStr};
+
+use serde::{de::IntoDeserializer, Deserialize, Deserializer};
+
+/// Deserializes `T` from either a JSON number or a string containing one.
+///
+/// Useful for integer (or otherwise `FromStr`-able) fields that the Lua side of the mod
+/// sometimes serializes as a string, e.g. `"42"` instead of `42`.
+pub fn string_or_number<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + FromStr,
+    T::Err: fmt::Display,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum StringOrNumber<T> {
+        Number(T),
+        String(String),
+    }
+
+    match StringOrNumber::<T>::deserialize(deserializer)? {
+        StringOrNumber::Number(n) => Ok(n),
+        StringOrNumber::String(s) => s.parse().map_err(serde::de::Error::custom),
+    }
+}
+
+/// Deserializes `T` (typically a unit-only enum) from a string tag, ignoring case and
+/// underscores/hyphens so e.g. `"SomeKind"`, `"some_kind"`, and `"some-kind"` all match a variant
+/// whose own `Deserialize` impl expects `"somekind"`.
+pub fn case_insensitive_enum<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de>,
+{
+    let raw = String::deserialize(deserializer)?;
+    let normalized: String = raw
+        .chars()
+        .filter(|c| *c != '_' && *c != '-')
+        .flat_map(char::to_lowercase)
+        .collect();
+
+    T::deserialize(normalized.as_str().into_deserializer())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::de::value::{Error as ValueError, StrDeserializer};
+    use serde_json::json;
+
+    use super::*;
+
+    #[test]
+    fn string_or_number_accepts_a_number() {
+        let n: u32 = string_or_number(json!(42)).unwrap();
+        assert_eq!(n, 42);
+    }
+
+    #[test]
+    fn string_or_number_accepts_a_numeric_string() {
+        let n: u32 = string_or_number(json!("42")).unwrap();
+        assert_eq!(n, 42);
+    }
+
+    #[derive(Deserialize, Debug, PartialEq, Eq)]
+    #[serde(rename_all = "lowercase")]
+    enum Kind {
+        SomeKind,
+    }
+
+    #[test]
+    fn case_insensitive_enum_ignores_case_and_separators() {
+        let deserializer: StrDeserializer<'_, ValueError> = "Some_Kind".into_deserializer();
+        let kind: Kind = case_insensitive_enum(deserializer).unwrap();
+        assert_eq!(kind, Kind::SomeKind);
+    }
+}
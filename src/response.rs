@@ -1,4 +1,4 @@
-use std::mem::MaybeUninit;
+use std::{convert::Infallible, fmt, str::FromStr};
 
 use crate::{
     call::ApiCall,
@@ -6,15 +6,175 @@ use crate::{
 };
 use serde::{
     de::{self, DeserializeOwned},
-    Deserialize,
+    Deserialize, Deserializer,
 };
+use serde_json::Value;
 
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Deserialize)]
-#[serde(rename_all = "lowercase", tag = "type", content = "data")]
+/// A structured HLAPI error, parsed out of the plain-text error messages the mod sends.
+///
+/// Recognizes the mod's canonical error shapes so callers can `matches!` on them instead of
+/// substring-matching a bare `String`; anything unrecognized falls back to `Other`.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum HlapiError {
+    /// The targeted device does not exist, or is no longer present.
+    UnknownDevice,
+    /// The targeted method does not exist on the device.
+    UnknownMethod,
+    /// The call passed a different number of arguments than the method expects.
+    BadArgumentCount { expected: usize, got: usize },
+    /// An argument did not have the type the method expects.
+    TypeMismatch,
+    /// An error message that didn't match any of the mod's recognized shapes.
+    Other(String),
+}
+
+impl fmt::Display for HlapiError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HlapiError::UnknownDevice => f.write_str("unknown device"),
+            HlapiError::UnknownMethod => f.write_str("unknown method"),
+            HlapiError::BadArgumentCount { expected, got } => {
+                write!(f, "bad argument count (expected {expected}, got {got})")
+            }
+            HlapiError::TypeMismatch => f.write_str("type mismatch"),
+            HlapiError::Other(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for HlapiError {}
+
+impl FromStr for HlapiError {
+    // Parsing never fails outright; messages that don't match a known shape fall back to `Other`.
+    type Err = Infallible;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let lower = s.to_ascii_lowercase();
+
+        if lower.contains("unknown device") || lower.contains("no such device") {
+            return Ok(HlapiError::UnknownDevice);
+        }
+
+        if lower.contains("unknown method") || lower.contains("no such method") {
+            return Ok(HlapiError::UnknownMethod);
+        }
+
+        if let Some(counts) = lower
+            .find("bad argument count")
+            .and_then(|_| parse_argument_counts(&lower))
+        {
+            return Ok(HlapiError::BadArgumentCount {
+                expected: counts.0,
+                got: counts.1,
+            });
+        }
+
+        if lower.contains("type mismatch") || lower.contains("wrong type") {
+            return Ok(HlapiError::TypeMismatch);
+        }
+
+        Ok(HlapiError::Other(s.to_owned()))
+    }
+}
+
+/// Parses `"...expected <n>, got <m>..."` out of a bad-argument-count message.
+fn parse_argument_counts(message: &str) -> Option<(usize, usize)> {
+    let expected_idx = message.find("expected")? + "expected".len();
+    let (_, after_expected) = message.split_at(expected_idx);
+    let expected: usize = after_expected
+        .trim_start()
+        .split(|c: char| !c.is_ascii_digit())
+        .find(|s| !s.is_empty())?
+        .parse()
+        .ok()?;
+
+    let got_idx = message.find("got")? + "got".len();
+    let (_, after_got) = message.split_at(got_idx);
+    let got: usize = after_got
+        .trim_start()
+        .split(|c: char| !c.is_ascii_digit())
+        .find(|s| !s.is_empty())?
+        .parse()
+        .ok()?;
+
+    Some((expected, got))
+}
+
+impl<'de> Deserialize<'de> for HlapiError {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let message = String::deserialize(deserializer)?;
+        // Infallible: `FromStr` always falls back to `Other` rather than erroring.
+        Ok(message.parse().unwrap())
+    }
+}
+
+/// Resolves a `T::Response` from the `data` field, given whether the field was present at all.
+///
+/// Unlike `Option<T>::deserialize`, which can't tell an absent `data` key apart from a present
+/// `data: null`, `data` here is already disambiguated at the map level by `RpcResponse`.
+pub(crate) trait FromDataField: Sized {
+    fn from_data_field<E: de::Error>(data: Option<Value>) -> Result<Self, E>;
+}
+
+impl<R: DeserializeOwned> FromDataField for R {
+    fn from_data_field<E: de::Error>(data: Option<Value>) -> Result<Self, E> {
+        let value = data.ok_or_else(|| de::Error::missing_field("data"))?;
+        serde_json::from_value(value).map_err(E::custom)
+    }
+}
+
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub enum RpcResponse<T: ApiCall> {
-    #[serde(alias = "list", alias = "methods", rename = "result")]
     Response(T::Response),
-    Error(String),
+    Error(HlapiError),
+}
+
+impl<'de, T: ApiCall> Deserialize<'de> for RpcResponse<T>
+where
+    T::Response: FromDataField,
+{
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Raw {
+            #[serde(rename = "type")]
+            kind: String,
+            #[serde(default, deserialize_with = "present")]
+            data: Option<Value>,
+        }
+
+        // Only called when the key was actually in the map (see the `#[serde(default)]` above),
+        // so `Value::deserialize` sees `null` as the real `Value::Null` rather than as "absent".
+        fn present<'de, D>(deserializer: D) -> Result<Option<Value>, D::Error>
+        where
+            D: Deserializer<'de>,
+        {
+            Value::deserialize(deserializer).map(Some)
+        }
+
+        let raw = Raw::deserialize(deserializer)?;
+
+        match raw.kind.as_str() {
+            "result" | "list" | "methods" => {
+                T::Response::from_data_field(raw.data).map(RpcResponse::Response)
+            }
+            "error" => {
+                let value = raw.data.ok_or_else(|| de::Error::missing_field("data"))?;
+                serde_json::from_value(value)
+                    .map(RpcResponse::Error)
+                    .map_err(de::Error::custom)
+            }
+            other => Err(de::Error::unknown_variant(
+                other,
+                &["result", "list", "methods", "error"],
+            )),
+        }
+    }
 }
 
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default, Deserialize)]
@@ -26,43 +186,72 @@ pub struct MethodsResponse(pub Vec<MethodDescriptor>);
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
 pub struct InvokeResponse<R>(pub R);
 
-impl<'de, R: DeserializeOwned + 'static> Deserialize<'de> for InvokeResponse<R> {
-    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
-    where
-        D: serde::Deserializer<'de>,
-    {
-        // Generates a zero-sized type 'from thin air'.
-        // When invoking a method in the HLAPI that doesn't have a return value, one might expect
-        // the response to look like `{"type": "result", "data": null}`, but in reality, it looks
-        // like `{"type": "result"}`, and is missing the data field. With a derived Deserialize,
-        // serde_json would complain about a missing `data` field, when it is actually supposed to
-        // be missing. This only happens in the case of the void return type, which is represented
-        // by zero-sized types (usually `()`) in Rust.
-        fn zst<T>() -> T {
-            assert!(std::mem::size_of::<T>() == 0, "`T` must be a ZST");
-
-            // SAFETY: The check above ensures that T is a zero-sized type, and thus can be constructed by
-            // reading a well-aligned pointer, even if that pointer doesn't point to anything valid.
-            #[allow(clippy::uninit_assumed_init)]
-            unsafe {
-                MaybeUninit::uninit().assume_init()
-            }
+impl<R: DeserializeOwned> FromDataField for InvokeResponse<R> {
+    fn from_data_field<E: de::Error>(data: Option<Value>) -> Result<Self, E> {
+        R::from_data_field(data).map(InvokeResponse)
+    }
+}
+
+/// Marker type for invoke calls that have no return value.
+///
+/// `{"type": "result"}` (no `data` field at all) means the call returned nothing; `{"type":
+/// "result", "data": null}` means it returned JSON `null`. Typing such a call as
+/// `InvokeResponse<()>` used to conflate the two, since `()` happily deserializes from either.
+/// `InvokeResponse<Empty>` only resolves for the former.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Empty;
+
+/// Alias for [`Empty`] for callers who find "no content" clearer than "empty".
+pub type NoContent = Empty;
+
+impl FromDataField for InvokeResponse<Empty> {
+    fn from_data_field<E: de::Error>(data: Option<Value>) -> Result<Self, E> {
+        match data {
+            None => Ok(InvokeResponse(Empty)),
+            Some(_) => Err(E::custom(
+                "expected the `data` field to be absent, found a value",
+            )),
         }
+    }
+}
 
-        let opt: Option<R> = Deserialize::deserialize(deserializer)?;
+/// The three ways an invoke result can come back: `data` absent, `data: null`, or `data: <value>`.
+///
+/// Devices that report "no change" by omitting `data` but "cleared" via `data: null` need these
+/// told apart, which plain `Option<R>` can't do (see [`FromDataField`]).
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum Field<T> {
+    /// The `data` field was absent entirely.
+    Missing,
+    /// The `data` field was present and explicitly `null`.
+    Null,
+    /// The `data` field was present with a value.
+    Value(T),
+}
 
-        match opt {
-            Some(r) => Ok(InvokeResponse(r)),
-            None if std::mem::size_of::<R>() == 0 => Ok(InvokeResponse(zst())),
-            // We actually do expect the `data` field if the return type is not actually zero-sized.
-            // If there's no `data` field when it was expected, that means something went wrong, and
-            // not just that the call didn't return anything.
-            None => Err(de::Error::missing_field("data")),
+impl<T> Field<T> {
+    /// Returns the contained value, or `None` if this is [`Field::Missing`] or [`Field::Null`].
+    pub fn value(self) -> Option<T> {
+        match self {
+            Field::Value(t) => Some(t),
+            Field::Missing | Field::Null => None,
         }
     }
 }
 
-impl<T: ApiCall> From<RpcResponse<T>> for Result<T::Response, String> {
+impl<R: DeserializeOwned> FromDataField for InvokeResponse<Field<R>> {
+    fn from_data_field<E: de::Error>(data: Option<Value>) -> Result<Self, E> {
+        let field = match data {
+            None => Field::Missing,
+            Some(Value::Null) => Field::Null,
+            Some(value) => Field::Value(serde_json::from_value(value).map_err(E::custom)?),
+        };
+
+        Ok(InvokeResponse(field))
+    }
+}
+
+impl<T: ApiCall> From<RpcResponse<T>> for Result<T::Response, HlapiError> {
     fn from(value: RpcResponse<T>) -> Self {
         match value {
             RpcResponse::Response(t) => Ok(t),
@@ -71,4 +260,153 @@ impl<T: ApiCall> From<RpcResponse<T>> for Result<T::Response, String> {
             RpcResponse::Error(err) => Err(err),
         }
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // `ApiCall` lives in `crate::call`, which isn't part of this checkout. Stand in a minimal
+    // local mock so `RpcResponse::deserialize` can be exercised through real JSON.
+    #[derive(Debug, PartialEq)]
+    struct Echo;
+
+    impl ApiCall for Echo {
+        type Response = InvokeResponse<Field<i32>>;
+    }
+
+    #[test]
+    fn rpc_response_result_with_missing_data_is_field_missing() {
+        let response: RpcResponse<Echo> = serde_json::from_str(r#"{"type":"result"}"#).unwrap();
+        let RpcResponse::Response(InvokeResponse(field)) = response else {
+            panic!("expected RpcResponse::Response");
+        };
+        assert_eq!(field, Field::Missing);
+    }
+
+    #[test]
+    fn rpc_response_result_with_null_data_is_field_null() {
+        let response: RpcResponse<Echo> =
+            serde_json::from_str(r#"{"type":"result","data":null}"#).unwrap();
+        let RpcResponse::Response(InvokeResponse(field)) = response else {
+            panic!("expected RpcResponse::Response");
+        };
+        assert_eq!(field, Field::Null);
+    }
+
+    #[test]
+    fn rpc_response_result_with_data_is_field_value() {
+        let response: RpcResponse<Echo> =
+            serde_json::from_str(r#"{"type":"result","data":42}"#).unwrap();
+        let RpcResponse::Response(InvokeResponse(field)) = response else {
+            panic!("expected RpcResponse::Response");
+        };
+        assert_eq!(field, Field::Value(42));
+    }
+
+    #[test]
+    fn rpc_response_accepts_list_and_methods_tags() {
+        for tag in ["list", "methods"] {
+            let json = format!(r#"{{"type":"{tag}","data":1}}"#);
+            let response: RpcResponse<Echo> = serde_json::from_str(&json).unwrap();
+            assert!(matches!(response, RpcResponse::Response(_)));
+        }
+    }
+
+    #[test]
+    fn rpc_response_error_parses_through_hlapi_error() {
+        let response: RpcResponse<Echo> =
+            serde_json::from_str(r#"{"type":"error","data":"unknown device 'foo'"}"#).unwrap();
+        assert_eq!(response, RpcResponse::Error(HlapiError::UnknownDevice));
+    }
+
+    #[test]
+    fn empty_resolves_only_when_data_is_absent() {
+        InvokeResponse::<Empty>::from_data_field::<serde_json::Error>(None)
+            .expect("a missing `data` field should resolve to `Empty`");
+    }
+
+    #[test]
+    fn empty_rejects_explicit_null() {
+        InvokeResponse::<Empty>::from_data_field::<serde_json::Error>(Some(Value::Null))
+            .expect_err("an explicit `data: null` should not resolve to `Empty`");
+    }
+
+    #[test]
+    fn empty_rejects_a_real_value() {
+        InvokeResponse::<Empty>::from_data_field::<serde_json::Error>(Some(Value::from(1)))
+            .expect_err("a present `data` value should not resolve to `Empty`");
+    }
+
+    #[test]
+    fn field_is_missing_when_data_is_absent() {
+        let InvokeResponse(field) =
+            InvokeResponse::<Field<i32>>::from_data_field::<serde_json::Error>(None).unwrap();
+        assert_eq!(field, Field::Missing);
+    }
+
+    #[test]
+    fn field_is_null_on_explicit_null() {
+        let InvokeResponse(field) =
+            InvokeResponse::<Field<i32>>::from_data_field::<serde_json::Error>(Some(Value::Null))
+                .unwrap();
+        assert_eq!(field, Field::Null);
+    }
+
+    #[test]
+    fn hlapi_error_recognizes_known_shapes() {
+        assert_eq!(
+            "Unknown device 'foo'".parse::<HlapiError>().unwrap(),
+            HlapiError::UnknownDevice
+        );
+        assert_eq!(
+            "No such method 'bar'".parse::<HlapiError>().unwrap(),
+            HlapiError::UnknownMethod
+        );
+        assert_eq!(
+            "Bad argument count (expected 2, got 3)"
+                .parse::<HlapiError>()
+                .unwrap(),
+            HlapiError::BadArgumentCount {
+                expected: 2,
+                got: 3
+            }
+        );
+        assert_eq!(
+            "Type mismatch on argument 1".parse::<HlapiError>().unwrap(),
+            HlapiError::TypeMismatch
+        );
+    }
+
+    #[test]
+    fn hlapi_error_falls_back_to_other() {
+        assert_eq!(
+            "something went sideways".parse::<HlapiError>().unwrap(),
+            HlapiError::Other("something went sideways".to_owned())
+        );
+    }
+
+    #[test]
+    fn parse_argument_counts_extracts_both_numbers() {
+        assert_eq!(
+            parse_argument_counts("bad argument count (expected 2, got 3)"),
+            Some((2, 3))
+        );
+    }
+
+    #[test]
+    fn parse_argument_counts_rejects_malformed_messages() {
+        assert_eq!(parse_argument_counts("bad argument count"), None);
+        assert_eq!(parse_argument_counts("expected a number, got nothing"), None);
+    }
+
+    #[test]
+    fn field_is_value_on_a_real_value() {
+        let InvokeResponse(field) =
+            InvokeResponse::<Field<i32>>::from_data_field::<serde_json::Error>(Some(Value::from(
+                42,
+            )))
+            .unwrap();
+        assert_eq!(field, Field::Value(42));
+    }
 }
\ No newline at end of file